@@ -0,0 +1,100 @@
+use cgmath::{InnerSpace, Matrix4, Point3, Quaternion, Rad, Rotation3, Vector3};
+use std::collections::HashSet;
+use winit::event::VirtualKeyCode;
+
+/// cgmath's `perspective`/`PerspectiveFov` produce an OpenGL-style clip volume
+/// with z in `[-1, 1]`, but wgpu expects z in `[0, 1]`. Premultiplying the
+/// view-projection matrix by this rescales clip-space z into wgpu's range.
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+pub struct Camera {
+    pub eye: Point3<f32>,
+    pub dir: Vector3<f32>,
+    pub up: Vector3<f32>,
+    pub aspect: f32,
+    pub fovy: Rad<f32>,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera {
+    pub fn build_view_projection_matrix(&self) -> Matrix4<f32> {
+        let view = Matrix4::look_at_dir(self.eye, self.dir, self.up);
+        let proj = cgmath::perspective(self.fovy, self.aspect, self.near, self.far);
+
+        OPENGL_TO_WGPU_MATRIX * proj * view
+    }
+}
+
+/// Owns the WASD/Space/Shift fly controls and mouse-look state, and applies
+/// them to a `Camera` once per frame.
+pub struct CameraController {
+    speed: f32,
+    pressed_keys: HashSet<VirtualKeyCode>,
+}
+
+impl CameraController {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            pressed_keys: HashSet::new(),
+        }
+    }
+
+    pub fn process_keyboard(&mut self, keycode: VirtualKeyCode, pressed: bool) {
+        if pressed {
+            self.pressed_keys.insert(keycode);
+        } else {
+            self.pressed_keys.remove(&keycode);
+        }
+    }
+
+    pub fn process_mouse(&self, camera: &mut Camera, delta_x: f64, delta_y: f64) {
+        let right = camera.dir.cross(camera.up).normalize();
+
+        camera.dir =
+            Quaternion::from_angle_y(Rad(-delta_x as f32 / 300.0)).rotate_vector(camera.dir);
+        camera.dir = Quaternion::from_axis_angle(right, Rad(delta_y as f32 / 300.0))
+            .rotate_vector(camera.dir);
+        camera.dir = camera.dir.normalize();
+    }
+
+    pub fn process_scroll(&mut self, factor: f32) {
+        self.speed *= factor.min(4.0).max(0.25);
+        self.speed = self.speed.min(1E10).max(1E6);
+    }
+
+    pub fn update_camera(&self, camera: &mut Camera) {
+        let right = camera.dir.cross(camera.up).normalize();
+
+        if self.pressed_keys.contains(&VirtualKeyCode::A) {
+            camera.eye += -right * self.speed;
+        }
+
+        if self.pressed_keys.contains(&VirtualKeyCode::D) {
+            camera.eye += right * self.speed;
+        }
+
+        if self.pressed_keys.contains(&VirtualKeyCode::W) {
+            camera.eye += camera.dir * self.speed;
+        }
+
+        if self.pressed_keys.contains(&VirtualKeyCode::S) {
+            camera.eye += -camera.dir * self.speed;
+        }
+
+        if self.pressed_keys.contains(&VirtualKeyCode::Space) {
+            camera.eye.y -= self.speed;
+        }
+
+        if self.pressed_keys.contains(&VirtualKeyCode::LShift) {
+            camera.eye.y += self.speed;
+        }
+    }
+}
@@ -0,0 +1,24 @@
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Creates a depth texture sized to match the swap chain. Must be recreated
+/// whenever the swap chain is (i.e. on window resize).
+pub fn create_depth_texture_view(
+    device: &wgpu::Device,
+    swap_chain_descriptor: &wgpu::SwapChainDescriptor,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: swap_chain_descriptor.width,
+            height: swap_chain_descriptor.height,
+            depth: 1,
+        },
+        array_layer_count: 1,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+    });
+
+    texture.create_default_view()
+}
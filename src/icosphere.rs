@@ -0,0 +1,101 @@
+use cgmath::{InnerSpace, Vector3};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct Vertex {
+    pub position: [f32; 3],
+}
+
+// Golden-ratio derived icosahedron, already unit length.
+const X: f32 = 0.525_731_1;
+const Z: f32 = 0.850_650_8;
+const N: f32 = 0.0;
+
+const BASE_VERTICES: [[f32; 3]; 12] = [
+    [-X, N, Z],
+    [X, N, Z],
+    [-X, N, -Z],
+    [X, N, -Z],
+    [N, Z, X],
+    [N, Z, -X],
+    [N, -Z, X],
+    [N, -Z, -X],
+    [Z, X, N],
+    [-Z, X, N],
+    [Z, -X, N],
+    [-Z, -X, N],
+];
+
+const BASE_INDICES: [[u16; 3]; 20] = [
+    [0, 4, 1],
+    [0, 9, 4],
+    [9, 5, 4],
+    [4, 5, 8],
+    [4, 8, 1],
+    [8, 10, 1],
+    [8, 3, 10],
+    [5, 3, 8],
+    [5, 2, 3],
+    [2, 7, 3],
+    [7, 10, 3],
+    [7, 6, 10],
+    [7, 11, 6],
+    [11, 0, 6],
+    [0, 1, 6],
+    [6, 1, 10],
+    [9, 0, 11],
+    [9, 11, 2],
+    [9, 2, 5],
+    [7, 2, 11],
+];
+
+/// Builds a unit icosphere by recursively subdividing an icosahedron,
+/// returning its (deduplicated) vertex positions and triangle index list.
+pub fn generate(subdivisions: u32) -> (Vec<Vertex>, Vec<u16>) {
+    let mut vertices: Vec<Vector3<f32>> = BASE_VERTICES.iter().map(|&v| Vector3::from(v)).collect();
+    let mut indices: Vec<u16> = BASE_INDICES.iter().flatten().copied().collect();
+
+    for _ in 0..subdivisions {
+        let mut midpoint_cache = HashMap::new();
+        let mut subdivided = Vec::with_capacity(indices.len() * 4);
+
+        for tri in indices.chunks(3) {
+            let (a, b, c) = (tri[0], tri[1], tri[2]);
+            let ab = midpoint(&mut vertices, &mut midpoint_cache, a, b);
+            let bc = midpoint(&mut vertices, &mut midpoint_cache, b, c);
+            let ca = midpoint(&mut vertices, &mut midpoint_cache, c, a);
+
+            subdivided.extend_from_slice(&[a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca]);
+        }
+
+        indices = subdivided;
+    }
+
+    let vertices = vertices
+        .into_iter()
+        .map(|position| Vertex {
+            position: position.into(),
+        })
+        .collect();
+
+    (vertices, indices)
+}
+
+fn midpoint(
+    vertices: &mut Vec<Vector3<f32>>,
+    cache: &mut HashMap<(u16, u16), u16>,
+    a: u16,
+    b: u16,
+) -> u16 {
+    let key = if a < b { (a, b) } else { (b, a) };
+    if let Some(&index) = cache.get(&key) {
+        return index;
+    }
+
+    let mid = ((vertices[a as usize] + vertices[b as usize]) * 0.5).normalize();
+    let index = vertices.len() as u16;
+    vertices.push(mid);
+    cache.insert(key, index);
+    index
+}
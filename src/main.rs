@@ -1,13 +1,25 @@
 use cgmath::prelude::*;
-use cgmath::{Matrix4, PerspectiveFov, Point3, Quaternion, Rad, Vector3};
+use cgmath::{Matrix4, Point3, Rad, Vector3};
 use rand::prelude::*;
-use std::collections::HashSet;
 use std::f32::consts::PI;
 use winit::{
     event,
     event_loop::{ControlFlow, EventLoop},
 };
 
+use camera::{Camera, CameraController};
+
+mod camera;
+mod depth;
+mod icosphere;
+mod model;
+mod texture;
+
+/// The galactic-core particles are pushed first, so they occupy indices
+/// `0..MESH_BODY_COUNT` in the particle buffer and render as the OBJ model
+/// instead of the glow-textured icosphere mesh used for the rest.
+const MESH_BODY_COUNT: u32 = 2;
+
 const G: f64 = 6.67408E-11;
 
 #[derive(Clone, Copy, Debug)]
@@ -60,7 +72,12 @@ fn generate_galaxy(particles: &mut Vec<Particle>, amount: u32, center: &Particle
         pos.x += radius * angle.cos();
         pos.y += radius * angle.sin();
 
-        let mass = 0E27;
+        // Give each star a small but nonzero mass so its `radius` (derived
+        // from mass/density in `Particle::new`) is actually nonzero and the
+        // billboard/mesh geometry scaled by it is visible — these masses are
+        // still dwarfed by the cores' 1E30, so they don't meaningfully
+        // perturb the orbit physics.
+        let mass = 1E20 + thread_rng().gen::<f64>() * 9E20;
         let density = 1.408;
 
         // Fg = Fg
@@ -110,17 +127,17 @@ fn main() {
     run(globals, particles);
 }
 
-fn build_matrix(pos: Point3<f32>, dir: Vector3<f32>, aspect: f32) -> Matrix4<f32> {
-    Matrix4::from(PerspectiveFov {
-        fovy: Rad(PI / 2.0),
-        aspect,
-        near: 0.01,
-        far: 1E25,
-    }) * Matrix4::look_at_dir(pos, dir, Vector3::new(0.0, 1.0, 0.0))
-        * Matrix4::from_translation(pos.to_vec())
-}
-
 fn run(mut globals: Globals, particles: Vec<Particle>) {
+    // The draw calls below assume the first `MESH_BODY_COUNT` particles are
+    // the galactic cores; catch a caller that reorders or shrinks `particles`
+    // below that count instead of silently mis-rendering.
+    assert!(
+        particles.len() >= MESH_BODY_COUNT as usize,
+        "need at least {} particles for the mesh-backed cores, got {}",
+        MESH_BODY_COUNT,
+        particles.len()
+    );
+
     let particles_size = (particles.len() * std::mem::size_of::<Particle>()) as u64;
 
     let event_loop = EventLoop::new();
@@ -174,19 +191,73 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
     });
 
     // Load vertex shader
-    let vs = include_str!("shader.vert");
+    let vs = include_str!("shader_sphere.vert");
     let vs_module = device.create_shader_module(
         &wgpu::read_spirv(glsl_to_spirv::compile(vs, glsl_to_spirv::ShaderType::Vertex).unwrap())
             .unwrap(),
     );
 
     // Load fragment shader
-    let fs = include_str!("shader.frag");
+    let fs = include_str!("shader_sphere.frag");
     let fs_module = device.create_shader_module(
         &wgpu::read_spirv(glsl_to_spirv::compile(fs, glsl_to_spirv::ShaderType::Fragment).unwrap())
             .unwrap(),
     );
 
+    // Load compute shader
+    let cs = include_str!("shader.comp");
+    let cs_module = device.create_shader_module(
+        &wgpu::read_spirv(glsl_to_spirv::compile(cs, glsl_to_spirv::ShaderType::Compute).unwrap())
+            .unwrap(),
+    );
+
+    const COMPUTE_LOCAL_SIZE: u32 = 256;
+    let compute_workgroup_count =
+        (particles.len() as u32 + COMPUTE_LOCAL_SIZE - 1) / COMPUTE_LOCAL_SIZE;
+
+    // Load the model vertex/fragment shaders used for the galactic cores
+    let model_vs = include_str!("shader_model.vert");
+    let model_vs_module = device.create_shader_module(
+        &wgpu::read_spirv(
+            glsl_to_spirv::compile(model_vs, glsl_to_spirv::ShaderType::Vertex).unwrap(),
+        )
+        .unwrap(),
+    );
+
+    let model_fs = include_str!("shader_model.frag");
+    let model_fs_module = device.create_shader_module(
+        &wgpu::read_spirv(
+            glsl_to_spirv::compile(model_fs, glsl_to_spirv::ShaderType::Fragment).unwrap(),
+        )
+        .unwrap(),
+    );
+
+    let core_mesh = model::load_mesh(&device, include_bytes!("../assets/core.obj"));
+
+    // Every non-core particle is drawn as an instance of a shared unit
+    // icosphere, scaled by its own radius and textured with the glow sprite
+    // (UV-mapped equirectangularly from the sphere's own vertex positions)
+    // in the vertex/fragment shaders above.
+    let (star_vertices, star_indices) = icosphere::generate(1);
+    let star_index_count = star_indices.len() as u32;
+
+    let star_vertex_buffer = device
+        .create_buffer_mapped(star_vertices.len(), wgpu::BufferUsage::VERTEX)
+        .fill_from_slice(&star_vertices);
+
+    let star_index_buffer = device
+        .create_buffer_mapped(star_indices.len(), wgpu::BufferUsage::INDEX)
+        .fill_from_slice(&star_indices);
+
+    let mut texture_load_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { todo: 0 });
+    let glow_texture = texture::Texture::from_bytes(
+        &device,
+        &mut texture_load_encoder,
+        include_bytes!("../assets/star_glow.png"),
+    );
+    queue.submit(&[texture_load_encoder.finish()]);
+
     // Create a new buffer
     let globals_buffer = device
         .create_buffer_mapped(1, wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST)
@@ -272,11 +343,61 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
         ],
     });
 
+    // Describe the glow-sprite texture bound in the fragment shader
+    let texture_bind_group_layout =
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            bindings: &[
+                wgpu::BindGroupLayoutBinding {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        multisampled: false,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                },
+                wgpu::BindGroupLayoutBinding {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler,
+                },
+            ],
+        });
+
+    let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &texture_bind_group_layout,
+        bindings: &[
+            wgpu::Binding {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&glow_texture.view),
+            },
+            wgpu::Binding {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&glow_texture.sampler),
+            },
+        ],
+    });
+
     // Combine all bind_group_layouts
     let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        bind_group_layouts: &[&bind_group_layout, &texture_bind_group_layout],
+    });
+
+    // The compute pass only ever touches the globals/particle storage
+    // buffers, so it gets its own (smaller) pipeline layout.
+    let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         bind_group_layouts: &[&bind_group_layout],
     });
 
+    // Integrates gravity for every particle each frame: reads `old_buffer`,
+    // writes the result into `current_buffer`.
+    let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        layout: &compute_pipeline_layout,
+        compute_stage: wgpu::ProgrammableStageDescriptor {
+            module: &cs_module,
+            entry_point: "main",
+        },
+    });
+
     // Describe the rendering process
     let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         layout: &pipeline_layout,
@@ -295,16 +416,105 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
             depth_bias_slope_scale: 0.0,
             depth_bias_clamp: 0.0,
         }),
-        primitive_topology: wgpu::PrimitiveTopology::PointList,
+        primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+        color_states: &[wgpu::ColorStateDescriptor {
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            // Additive blending: overlapping stars accumulate brightness
+            // instead of occluding each other.
+            color_blend: wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha_blend: wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            write_mask: wgpu::ColorWrite::ALL,
+        }],
+        depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
+            format: depth::DEPTH_FORMAT,
+            // Additively-blended glow quads still depth-test against each
+            // other's centers, but must not occlude what's behind them.
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil_front: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_back: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_read_mask: 0,
+            stencil_write_mask: 0,
+        }),
+        index_format: wgpu::IndexFormat::Uint16,
+        vertex_buffers: &[wgpu::VertexBufferDescriptor {
+            stride: std::mem::size_of::<icosphere::Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &[wgpu::VertexAttributeDescriptor {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Float3,
+            }],
+        }],
+        sample_count: 1,
+        sample_mask: !0,
+        alpha_to_coverage_enabled: false,
+    });
+
+    // Describe the rendering process for the handful of mesh-backed bodies
+    // (the galactic cores). It reuses the compute pass's smaller layout since
+    // it doesn't sample the glow texture.
+    let model_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        layout: &compute_pipeline_layout,
+        vertex_stage: wgpu::ProgrammableStageDescriptor {
+            module: &model_vs_module,
+            entry_point: "main",
+        },
+        fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+            module: &model_fs_module,
+            entry_point: "main",
+        }),
+        rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Ccw,
+            // core.obj's winding isn't guaranteed to match our front-face
+            // convention, and it's a single small hero asset rather than a
+            // sealed mesh we need backface culling to save fill rate on.
+            cull_mode: wgpu::CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+        }),
+        primitive_topology: wgpu::PrimitiveTopology::TriangleList,
         color_states: &[wgpu::ColorStateDescriptor {
             format: wgpu::TextureFormat::Bgra8UnormSrgb,
             color_blend: wgpu::BlendDescriptor::REPLACE,
             alpha_blend: wgpu::BlendDescriptor::REPLACE,
             write_mask: wgpu::ColorWrite::ALL,
         }],
-        depth_stencil_state: None,
+        depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
+            format: depth::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil_front: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_back: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_read_mask: 0,
+            stencil_write_mask: 0,
+        }),
         index_format: wgpu::IndexFormat::Uint16,
-        vertex_buffers: &[],
+        vertex_buffers: &[wgpu::VertexBufferDescriptor {
+            stride: std::mem::size_of::<model::ModelVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttributeDescriptor {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float3,
+                },
+                wgpu::VertexAttributeDescriptor {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float3,
+                },
+            ],
+        }],
         sample_count: 1,
         sample_mask: !0,
         alpha_to_coverage_enabled: false,
@@ -320,19 +530,20 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
 
     let mut swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
 
-    let mut camera_dir = -globals.camera_pos.to_vec();
-    camera_dir = camera_dir.normalize();
-    globals.matrix = build_matrix(
-        globals.camera_pos,
-        camera_dir,
-        size.width as f32 / size.height as f32,
-    );
-    let mut fly_speed = 3E7;
+    let mut depth_texture_view = depth::create_depth_texture_view(&device, &swap_chain_descriptor);
 
-    let mut pressed_keys = HashSet::new();
+    let mut camera = Camera {
+        eye: globals.camera_pos,
+        dir: (-globals.camera_pos.to_vec()).normalize(),
+        up: Vector3::new(0.0, 1.0, 0.0),
+        aspect: size.width as f32 / size.height as f32,
+        fovy: Rad(PI / 2.0),
+        near: 0.01,
+        far: 1E25,
+    };
+    globals.matrix = camera.build_view_projection_matrix();
 
-    let mut right = camera_dir.cross(Vector3::new(0.0, 1.0, 0.0));
-    right = right.normalize();
+    let mut camera_controller = CameraController::new(3E7);
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = if cfg!(feature = "metal-auto-capture") {
@@ -346,10 +557,7 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
                 event: event::DeviceEvent::MouseMotion { delta },
                 ..
             } => {
-                camera_dir = Quaternion::from_angle_y(Rad(-delta.0 as f32 / 300.0))
-                    .rotate_vector(camera_dir);
-                camera_dir = Quaternion::from_axis_angle(right, Rad(delta.1 as f32 / 300.0))
-                    .rotate_vector(camera_dir);
+                camera_controller.process_mouse(&mut camera, delta.0, delta.1);
             }
 
             event::Event::WindowEvent { event, .. } => match event {
@@ -414,7 +622,7 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
                         }
                         _ => {}
                     }
-                    pressed_keys.insert(keycode);
+                    camera_controller.process_keyboard(keycode, true);
                 }
 
                 // Release key
@@ -427,20 +635,18 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
                         },
                     ..
                 } => {
-                    pressed_keys.remove(&keycode);
+                    camera_controller.process_keyboard(keycode, false);
                 }
 
                 // Mouse scroll
                 event::WindowEvent::MouseWheel { delta, .. } => {
-                    fly_speed *= (1.0
-                        + (match delta {
+                    let factor = 1.0
+                        + match delta {
                             event::MouseScrollDelta::LineDelta(_, c) => c as f32 / 8.0,
                             event::MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 64.0,
-                        }))
-                    .min(4.0)
-                    .max(0.25);
+                        };
 
-                    fly_speed = fly_speed.min(1E10).max(1E6);
+                    camera_controller.process_scroll(factor);
                 }
 
                 // Resize window
@@ -450,6 +656,9 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
                     swap_chain_descriptor.width = physical.width.round() as u32;
                     swap_chain_descriptor.height = physical.height.round() as u32;
                     swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+                    depth_texture_view =
+                        depth::create_depth_texture_view(&device, &swap_chain_descriptor);
+                    camera.aspect = size.width as f32 / size.height as f32;
                 }
 
                 // Redraw
@@ -458,39 +667,9 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
                     let mut encoder =
                         device.create_command_encoder(&wgpu::CommandEncoderDescriptor { todo: 0 });
 
-                    camera_dir.normalize();
-                    right = camera_dir.cross(Vector3::new(0.0, 1.0, 0.0));
-                    right = right.normalize();
-
-                    if pressed_keys.contains(&event::VirtualKeyCode::A) {
-                        globals.camera_pos += -right * fly_speed;
-                    }
-
-                    if pressed_keys.contains(&event::VirtualKeyCode::D) {
-                        globals.camera_pos += right * fly_speed;
-                    }
-
-                    if pressed_keys.contains(&event::VirtualKeyCode::W) {
-                        globals.camera_pos += camera_dir * fly_speed;
-                    }
-
-                    if pressed_keys.contains(&event::VirtualKeyCode::S) {
-                        globals.camera_pos += -camera_dir * fly_speed;
-                    }
-
-                    if pressed_keys.contains(&event::VirtualKeyCode::Space) {
-                        globals.camera_pos.y -= fly_speed;
-                    }
-
-                    if pressed_keys.contains(&event::VirtualKeyCode::LShift) {
-                        globals.camera_pos.y += fly_speed;
-                    }
-
-                    globals.matrix = build_matrix(
-                        globals.camera_pos,
-                        camera_dir,
-                        size.width as f32 / size.height as f32,
-                    );
+                    camera_controller.update_camera(&mut camera);
+                    globals.camera_pos = camera.eye;
+                    globals.matrix = camera.build_view_projection_matrix();
 
                     // Create new globals buffer
                     let new_globals_buffer = device
@@ -514,6 +693,12 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
                         0,
                         particles_size,
                     );
+                    {
+                        let mut cpass = encoder.begin_compute_pass();
+                        cpass.set_pipeline(&compute_pipeline);
+                        cpass.set_bind_group(0, &bind_group, &[]);
+                        cpass.dispatch(compute_workgroup_count, 1, 1);
+                    }
                     {
                         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
@@ -528,11 +713,34 @@ fn run(mut globals: Globals, particles: Vec<Particle>) {
                                     a: 1.0,
                                 },
                             }],
-                            depth_stencil_attachment: None,
+                            depth_stencil_attachment: Some(
+                                wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                                    attachment: &depth_texture_view,
+                                    depth_load_op: wgpu::LoadOp::Clear,
+                                    depth_store_op: wgpu::StoreOp::Store,
+                                    clear_depth: 1.0,
+                                    stencil_load_op: wgpu::LoadOp::Clear,
+                                    stencil_store_op: wgpu::StoreOp::Store,
+                                    clear_stencil: 0,
+                                },
+                            ),
                         });
+                        rpass.set_pipeline(&model_render_pipeline);
+                        rpass.set_bind_group(0, &bind_group, &[]);
+                        rpass.set_index_buffer(&core_mesh.index_buffer, 0);
+                        rpass.set_vertex_buffers(0, &[(&core_mesh.vertex_buffer, 0)]);
+                        rpass.draw_indexed(0..core_mesh.index_count, 0, 0..MESH_BODY_COUNT);
+
                         rpass.set_pipeline(&render_pipeline);
                         rpass.set_bind_group(0, &bind_group, &[]);
-                        rpass.draw(0..particles.len() as u32, 0..1);
+                        rpass.set_bind_group(1, &texture_bind_group, &[]);
+                        rpass.set_index_buffer(&star_index_buffer, 0);
+                        rpass.set_vertex_buffers(0, &[(&star_vertex_buffer, 0)]);
+                        rpass.draw_indexed(
+                            0..star_index_count,
+                            0,
+                            MESH_BODY_COUNT..particles.len() as u32,
+                        );
                     }
 
                     queue.submit(&[encoder.finish()]);
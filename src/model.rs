@@ -0,0 +1,70 @@
+use cgmath::{InnerSpace, Vector3};
+use std::io::Cursor;
+
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ModelVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+}
+
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+}
+
+/// Parses an OBJ file already in memory (e.g. via `include_bytes!`) into a
+/// single GPU-ready mesh. Only the first object in the file is used — this
+/// is aimed at single-mesh "hero" assets like the galactic core model, not
+/// multi-part scenes.
+pub fn load_mesh(device: &wgpu::Device, obj_bytes: &[u8]) -> Mesh {
+    let mut reader = Cursor::new(obj_bytes);
+    let (models, _materials) =
+        tobj::load_obj_buf(&mut reader, |_| Ok((Vec::new(), Default::default())))
+            .expect("failed to parse OBJ model");
+
+    let mesh = &models.first().expect("OBJ file contained no meshes").mesh;
+
+    let vertices: Vec<ModelVertex> = (0..mesh.positions.len() / 3)
+        .map(|i| {
+            let position = [
+                mesh.positions[i * 3],
+                mesh.positions[i * 3 + 1],
+                mesh.positions[i * 3 + 2],
+            ];
+
+            // Meshes without `vn` lines fall back to the position direction
+            // as a normal, which is a reasonable approximation for roughly
+            // convex, origin-centered hero assets and avoids normalizing a
+            // zero vector (NaN) in the fragment shader.
+            let normal = if mesh.normals.is_empty() {
+                Vector3::from(position).normalize().into()
+            } else {
+                [
+                    mesh.normals[i * 3],
+                    mesh.normals[i * 3 + 1],
+                    mesh.normals[i * 3 + 2],
+                ]
+            };
+
+            ModelVertex { position, normal }
+        })
+        .collect();
+
+    let indices: Vec<u16> = mesh.indices.iter().map(|&i| i as u16).collect();
+
+    let vertex_buffer = device
+        .create_buffer_mapped(vertices.len(), wgpu::BufferUsage::VERTEX)
+        .fill_from_slice(&vertices);
+
+    let index_buffer = device
+        .create_buffer_mapped(indices.len(), wgpu::BufferUsage::INDEX)
+        .fill_from_slice(&indices);
+
+    Mesh {
+        vertex_buffer,
+        index_buffer,
+        index_count: indices.len() as u32,
+    }
+}
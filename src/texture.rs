@@ -0,0 +1,91 @@
+/// A GPU texture plus the sampler used to read it, loaded from an in-memory
+/// image (PNG, etc. — anything the `image` crate decodes).
+pub struct Texture {
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+impl Texture {
+    pub fn from_bytes(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        bytes: &[u8],
+    ) -> Self {
+        let image = image::load_from_memory(bytes).unwrap().to_rgba();
+        let (width, height) = image.dimensions();
+
+        let texture_extent = wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: texture_extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+
+        // wgpu requires each copied row to be a multiple of 256 bytes, which
+        // `4 * width` only satisfies by coincidence — pad every row out to
+        // that boundary in a staging buffer before the copy.
+        const ROW_ALIGNMENT: u32 = 256;
+        let unpadded_bytes_per_row = 4 * width;
+        let padding = (ROW_ALIGNMENT - unpadded_bytes_per_row % ROW_ALIGNMENT) % ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+
+        let mut padded_image = vec![0u8; (padded_bytes_per_row * height) as usize];
+        for row in 0..height {
+            let src_start = (row * unpadded_bytes_per_row) as usize;
+            let src_end = src_start + unpadded_bytes_per_row as usize;
+            let dst_start = (row * padded_bytes_per_row) as usize;
+            let dst_end = dst_start + unpadded_bytes_per_row as usize;
+
+            padded_image[dst_start..dst_end].copy_from_slice(&image[src_start..src_end]);
+        }
+
+        let buffer = device
+            .create_buffer_mapped(padded_image.len(), wgpu::BufferUsage::COPY_SRC)
+            .fill_from_slice(&padded_image);
+
+        encoder.copy_buffer_to_texture(
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                offset: 0,
+                row_pitch: padded_bytes_per_row,
+                image_height: height,
+            },
+            wgpu::TextureCopyView {
+                texture: &texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+            },
+            texture_extent,
+        );
+
+        let view = texture.create_default_view();
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: -100.0,
+            lod_max_clamp: 100.0,
+            compare_function: wgpu::CompareFunction::Always,
+        });
+
+        Self { view, sampler }
+    }
+}